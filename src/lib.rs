@@ -40,6 +40,19 @@
 //! covered by the ignore glob `lang_tests/*.lang`), but adding a new file such as `build.rs` will
 //! trigger a rebuild (since it is not covered by an ignore glob).
 //!
+//! If an ignore glob does not match any file in the tree -- for example because of a typo, or
+//! because the file it was meant to exclude was renamed -- a `cargo:warning=` is emitted naming
+//! the glob, since such a glob is silently a no-op and defeats the point of excluding it.
+//!
+//! Each root is walked in parallel (the emitted set is still sorted and de-duplicated, so it is
+//! reproducible across runs), which matters on crates with very large source or asset trees.
+//!
+//! The glob list has the full precedence semantics of `gitignore`: later entries win, so a
+//! `!pattern` entry re-includes a path an earlier, broader entry excluded (e.g. ignore
+//! `assets/**` except `assets/schema.json` with `&["assets/**", "!assets/schema.json"]`). You can
+//! also layer an extra project-wide ignore file (e.g. a committed `.rerunignore`) underneath the
+//! inline globs with [`RerunExcept::add_ignore_file`].
+//!
 //! To use `rerun_except` in this manner you simply need to call `rerun_except::rerun_except` with
 //! an array of ignore globs in [`gitignore` format](https://git-scm.com/docs/gitignore) as part of
 //! your `build.rs` file:
@@ -51,63 +64,482 @@
 //!     rerun_except(&["lang_tests/*.lang"]).unwrap();
 //! }
 //! ```
+//!
+//! If you need more control over the underlying walk -- for example to also consider hidden
+//! files, or to ignore an extra project-specific ignore file -- use [`RerunExcept`] directly:
+//!
+//! ```rust,ignore
+//! use rerun_except::RerunExcept;
+//!
+//! fn main() {
+//!     RerunExcept::new()
+//!         .globs(&["lang_tests/*.lang"])
+//!         .hidden(false)
+//!         .add_custom_ignore_filename(".buildignore")
+//!         .emit()
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! `cargo`'s own change detection only considers the package directory and its subdirectories, so
+//! build inputs that live elsewhere (generated sources, a sibling `frontend/` tree, vendored C
+//! code) are never watched. If you have such inputs, use [`rerun_except_in`] to walk one or more
+//! extra roots in addition to `CARGO_MANIFEST_DIR`:
+//!
+//! ```rust,ignore
+//! use rerun_except::rerun_except_in;
+//!
+//! fn main() {
+//!     rerun_except_in(&["../frontend"], &["lang_tests/*.lang"]).unwrap();
+//! }
+//! ```
+//!
+//! If you just want to know which files would be watched -- for example in a test -- use
+//! [`files_except`] (or [`RerunExcept::files`]), which returns the file list without emitting
+//! anything.
 
 #![allow(clippy::needless_doctest_main)]
 
+use std::collections::BTreeSet;
 use std::env;
 use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-use ignore::{overrides::OverrideBuilder, WalkBuilder};
+use ignore::{gitignore::GitignoreBuilder, WalkBuilder, WalkState};
 
-/// Specify which files should not cause `cargo` to rebuild a project. `globs` is an array of
-/// ignore globs. Each entry must be in [`gitignore` format](https://git-scm.com/docs/gitignore)
-/// with the minor exception that entries must not begin with a `!`.
-pub fn rerun_except(globs: &[&str]) -> Result<(), Box<dyn Error>> {
-    check_globs(globs)?;
-
-    let mdir = env::var("CARGO_MANIFEST_DIR").unwrap();
-    let mut overb = OverrideBuilder::new(&mdir);
-    for g in globs {
-        overb.add(&format!("!{}", g))?;
-    }
-    for e in WalkBuilder::new(&mdir)
-        .overrides(overb.build()?)
-        .build()
-        .filter(|x| x.is_ok())
-    {
-        let e_uw = e?;
-        let path = e_uw.path();
-        if path.is_dir() {
-            continue;
+/// A builder for configuring and running a `rerun_except` walk.
+///
+/// This exposes the underlying [`ignore::WalkBuilder`] knobs that [`rerun_except`] otherwise
+/// hard-codes to their defaults, so that projects which deliberately rely on hidden files, parent
+/// `.gitignore`s, global git excludes, symlinks, or an extra project-specific ignore file can opt
+/// into exactly the matcher semantics they need.
+pub struct RerunExcept {
+    globs: Vec<String>,
+    roots: Vec<PathBuf>,
+    hidden: bool,
+    parents: bool,
+    git_global: bool,
+    git_exclude: bool,
+    follow_links: bool,
+    custom_ignore_filenames: Vec<String>,
+    ignore_files: Vec<PathBuf>,
+}
+
+impl Default for RerunExcept {
+    fn default() -> Self {
+        RerunExcept {
+            globs: Vec::new(),
+            roots: Vec::new(),
+            hidden: true,
+            parents: true,
+            git_global: true,
+            git_exclude: true,
+            follow_links: false,
+            custom_ignore_filenames: Vec::new(),
+            ignore_files: Vec::new(),
         }
-        if let Some(path_str) = path.to_str() {
-            if path_str == mdir {
-                continue;
+    }
+}
+
+impl RerunExcept {
+    /// Create a new `RerunExcept` with the same defaults `rerun_except` uses.
+    pub fn new() -> Self {
+        RerunExcept::default()
+    }
+
+    /// Set the ordered ignore globs. Each entry is in
+    /// [`gitignore` format](https://git-scm.com/docs/gitignore), including `!pattern` entries,
+    /// which re-include a path that an earlier pattern excluded (later entries take precedence,
+    /// exactly as in a `gitignore` file).
+    pub fn globs(mut self, globs: &[&str]) -> Self {
+        self.globs = globs.iter().map(|g| (*g).to_owned()).collect();
+        self
+    }
+
+    /// Set extra root directories to walk, in addition to `CARGO_MANIFEST_DIR`, which is always
+    /// walked regardless of whether this is called.
+    pub fn roots<P: AsRef<Path>>(mut self, roots: &[P]) -> Self {
+        self.roots = roots.iter().map(|p| p.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// Whether to ignore hidden files and directories. Enabled by default.
+    pub fn hidden(mut self, yes: bool) -> Self {
+        self.hidden = yes;
+        self
+    }
+
+    /// Whether to read `.gitignore` files from parent directories. Enabled by default.
+    pub fn parents(mut self, yes: bool) -> Self {
+        self.parents = yes;
+        self
+    }
+
+    /// Whether to respect a global `gitignore` file, whose path is specified in git's
+    /// `core.excludesFile` config option. Enabled by default.
+    pub fn git_global(mut self, yes: bool) -> Self {
+        self.git_global = yes;
+        self
+    }
+
+    /// Whether to respect a repository's `.git/info/exclude` file. Enabled by default.
+    pub fn git_exclude(mut self, yes: bool) -> Self {
+        self.git_exclude = yes;
+        self
+    }
+
+    /// Whether to follow symbolic links while walking. Disabled by default.
+    pub fn follow_links(mut self, yes: bool) -> Self {
+        self.follow_links = yes;
+        self
+    }
+
+    /// Register an additional ignore file name (e.g. `.buildignore`) to be respected in every
+    /// directory visited by the walk, in addition to the usual `.gitignore` and `.ignore` files.
+    pub fn add_custom_ignore_filename<S: AsRef<str>>(mut self, file_name: S) -> Self {
+        self.custom_ignore_filenames
+            .push(file_name.as_ref().to_owned());
+        self
+    }
+
+    /// Register an extra project ignore file (e.g. a committed `.rerunignore`), in `gitignore`
+    /// format. Its patterns are layered underneath the inline globs: the inline globs (including
+    /// any `!` re-inclusions) always take precedence, with this file supplying further
+    /// exclusions for paths the inline globs don't otherwise mention.
+    pub fn add_ignore_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.ignore_files.push(path.as_ref().to_owned());
+        self
+    }
+
+    /// Walk the configured roots, returning the sorted, de-duplicated set of non-ignored files
+    /// together with, for each of `self.globs` by index, whether it matched at least one path.
+    fn walk(&self) -> Result<(Vec<PathBuf>, Vec<bool>), Box<dyn Error>> {
+        let mut roots = vec![PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())];
+        roots.extend(self.roots.clone());
+
+        let mut paths = BTreeSet::new();
+        let mut glob_matched = vec![false; self.globs.len()];
+        for root in &roots {
+            // `self.globs` is built with `GitignoreBuilder`, not `OverrideBuilder`: overrides use
+            // "whitelist" matching, where adding even one non-negated pattern (as a `!`
+            // re-include necessarily is) puts the whole matcher into include-only mode and
+            // silently ignores every file that doesn't match any pattern. A `Gitignore` gives us
+            // real, order-dependent `gitignore` precedence without that trap.
+            let mut inlineb = GitignoreBuilder::new(root);
+            for g in &self.globs {
+                inlineb.add_line(None, g)?;
+            }
+            let inline = Arc::new(inlineb.build()?);
+
+            let mut layeredb = GitignoreBuilder::new(root);
+            for f in &self.ignore_files {
+                if let Some(err) = layeredb.add(f) {
+                    return Err(Box::new(err));
+                }
+            }
+            let layered = Arc::new(layeredb.build()?);
+
+            let root_path = root.clone();
+            let globs = Arc::new(self.globs.clone());
+
+            let mut wb = WalkBuilder::new(root);
+            wb.hidden(self.hidden)
+                .parents(self.parents)
+                .git_global(self.git_global)
+                .git_exclude(self.git_exclude)
+                .follow_links(self.follow_links);
+            for file_name in &self.custom_ignore_filenames {
+                wb.add_custom_ignore_filename(file_name);
+            }
+
+            let found_paths: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+            let found_matched: Mutex<Vec<bool>> = Mutex::new(vec![false; self.globs.len()]);
+
+            wb.build_parallel().run(|| {
+                let inline = Arc::clone(&inline);
+                let layered = Arc::clone(&layered);
+                let globs = Arc::clone(&globs);
+                let root_path = root_path.clone();
+                let found_paths = &found_paths;
+                let found_matched = &found_matched;
+                Box::new(move |result| {
+                    if let Ok(entry) = result {
+                        let path = entry.path();
+                        if !path.is_dir() && path != root_path {
+                            let ignored = match inline.matched_path_or_any_parents(path, false) {
+                                ignore::Match::Ignore(glob) => {
+                                    mark_matched(found_matched, &globs, glob.original());
+                                    true
+                                }
+                                ignore::Match::Whitelist(glob) => {
+                                    mark_matched(found_matched, &globs, glob.original());
+                                    false
+                                }
+                                ignore::Match::None => {
+                                    layered.matched_path_or_any_parents(path, false).is_ignore()
+                                }
+                            };
+                            if !ignored {
+                                found_paths.lock().unwrap().push(path.to_owned());
+                            }
+                        }
+                    }
+                    WalkState::Continue
+                })
+            });
+
+            paths.extend(found_paths.into_inner().unwrap());
+            for (i, m) in found_matched.into_inner().unwrap().into_iter().enumerate() {
+                glob_matched[i] |= m;
             }
-            println!("cargo:rerun-if-changed={}", path_str);
         }
+
+        Ok((paths.into_iter().collect(), glob_matched))
     }
 
-    Ok(())
-}
+    /// Compute the sorted, de-duplicated list of files that would be watched, without emitting
+    /// any `cargo:rerun-if-changed=` or `cargo:warning=` lines. This is the pure counterpart to
+    /// [`RerunExcept::emit`] and is useful for testing, or for callers who want to post-process
+    /// the paths themselves.
+    pub fn files(self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.walk().map(|(paths, _)| paths)
+    }
+
+    /// Run the walk and emit `cargo:rerun-if-changed=` lines for every file found, as well as a
+    /// `cargo:warning=` for every ignore glob that did not match any file (a likely typo, since
+    /// such a glob silently becomes a no-op).
+    pub fn emit(self) -> Result<(), Box<dyn Error>> {
+        let globs = self.globs.clone();
+        let (paths, glob_matched) = self.walk()?;
+
+        for path in &paths {
+            if let Some(path_str) = path.to_str() {
+                println!("cargo:rerun-if-changed={}", path_str);
+            }
+        }
 
-fn check_globs(globs: &[&str]) -> Result<(), Box<dyn Error>> {
-    for g in globs {
-        if g.starts_with('!') {
-            return Err(Box::<dyn Error>::from("Glob '%s' starts with a '!'"));
+        for (g, matched) in globs.iter().zip(glob_matched.iter()) {
+            if !matched {
+                println!("cargo:warning=ignore glob '{}' did not match any files", g);
+            }
         }
+
+        Ok(())
+    }
+}
+
+/// Specify which files should not cause `cargo` to rebuild a project. `globs` is an ordered array
+/// of ignore globs in [`gitignore` format](https://git-scm.com/docs/gitignore), so a `!pattern`
+/// entry re-includes a path excluded by an earlier entry.
+pub fn rerun_except(globs: &[&str]) -> Result<(), Box<dyn Error>> {
+    RerunExcept::new().globs(globs).emit()
+}
+
+/// Like [`rerun_except`], but also walks `roots` in addition to `CARGO_MANIFEST_DIR`, emitting
+/// the union of non-ignored files found across all of them. This is useful for build inputs that
+/// live outside the package directory, which `cargo`'s own change detection never considers. If
+/// `roots` is empty this is equivalent to `rerun_except`.
+pub fn rerun_except_in<P: AsRef<Path>>(roots: &[P], globs: &[&str]) -> Result<(), Box<dyn Error>> {
+    RerunExcept::new().roots(roots).globs(globs).emit()
+}
+
+/// Compute the sorted, de-duplicated list of files that `rerun_except` would watch, without
+/// emitting any `cargo:rerun-if-changed=` lines. `globs` has the same meaning as in
+/// [`rerun_except`].
+pub fn files_except(globs: &[&str]) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    RerunExcept::new().globs(globs).files()
+}
+
+/// Record, in `found_matched`, that the user-supplied glob with text `original` fired during the
+/// walk, so that [`RerunExcept::emit`] can later warn about globs that never matched anything.
+fn mark_matched(found_matched: &Mutex<Vec<bool>>, globs: &[String], original: &str) {
+    if let Some(idx) = globs.iter().position(|g| g == original) {
+        found_matched.lock().unwrap()[idx] = true;
     }
-    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
+
+    // Tests that read the default `CARGO_MANIFEST_DIR` root serialise on this lock, since
+    // setting the env var is process-global and `cargo test` runs tests in parallel threads.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("rerun_except_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_fixture(root: &Path, files: &[(&str, &str)]) {
+        for (rel, contents) in files {
+            let path = root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    fn with_manifest_dir<T>(dir: &Path, f: impl FnOnce() -> T) -> T {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let old = std::env::var("CARGO_MANIFEST_DIR").ok();
+        std::env::set_var("CARGO_MANIFEST_DIR", dir);
+        let result = f();
+        match old {
+            Some(v) => std::env::set_var("CARGO_MANIFEST_DIR", v),
+            None => std::env::remove_var("CARGO_MANIFEST_DIR"),
+        }
+        result
+    }
+
+    #[test]
+    fn test_files_except_fixture() {
+        let dir = temp_dir("files_except");
+        write_fixture(
+            &dir,
+            &[
+                ("build.rs", ""),
+                ("lang_tests/run.rs", ""),
+                ("lang_tests/test1.lang", ""),
+                ("lang_tests/test2.lang", ""),
+            ],
+        );
+
+        let mut files = with_manifest_dir(&dir, || files_except(&["lang_tests/*.lang"]).unwrap());
+        files.sort();
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(
+            files,
+            vec![dir.join("build.rs"), dir.join("lang_tests").join("run.rs")]
+        );
+    }
+
+    #[test]
+    fn test_hidden_false_includes_dotfiles() {
+        let dir = temp_dir("hidden_false");
+        write_fixture(&dir, &[("build.rs", ""), (".env", "")]);
+
+        let mut files =
+            with_manifest_dir(&dir, || RerunExcept::new().hidden(false).files().unwrap());
+        files.sort();
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(files, vec![dir.join(".env"), dir.join("build.rs")]);
+    }
+
+    #[test]
+    fn test_custom_ignore_filename_is_honoured() {
+        let dir = temp_dir("custom_ignore_filename");
+        write_fixture(
+            &dir,
+            &[
+                ("build.rs", ""),
+                ("vendor/drop.c", ""),
+                (".buildignore", "vendor/\n"),
+            ],
+        );
+
+        let files = with_manifest_dir(&dir, || {
+            RerunExcept::new()
+                .add_custom_ignore_filename(".buildignore")
+                .files()
+                .unwrap()
+        });
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(files, vec![dir.join("build.rs")]);
+    }
+
+    #[test]
+    fn test_mark_matched() {
+        let found_matched = Mutex::new(vec![false, false]);
+        let globs = vec!["a".to_owned(), "!b".to_owned()];
+
+        mark_matched(&found_matched, &globs, "a");
+        mark_matched(&found_matched, &globs, "!b");
+        mark_matched(&found_matched, &globs, "unrelated");
+
+        assert_eq!(found_matched.into_inner().unwrap(), vec![true, true]);
+    }
 
     #[test]
-    fn test_check_globs() {
-        assert!(check_globs(&["a"]).is_ok());
-        assert!(check_globs(&["^a"]).is_ok());
-        assert!(check_globs(&["!a"]).is_err());
+    fn test_glob_matched_tracking() {
+        let dir = temp_dir("glob_matched");
+        write_fixture(&dir, &[("lang_tests/test1.lang", "")]);
+
+        let (_, matched) = with_manifest_dir(&dir, || {
+            RerunExcept::new()
+                .globs(&["lang_tests/*.lang", "no/such/path/*.missing"])
+                .walk()
+                .unwrap()
+        });
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(matched, vec![true, false]);
+    }
+
+    #[test]
+    fn test_reinclude_precedence() {
+        let dir = temp_dir("reinclude");
+        write_fixture(
+            &dir,
+            &[
+                ("build.rs", ""),
+                ("assets/keep.json", ""),
+                ("assets/drop.bin", ""),
+            ],
+        );
+
+        let mut files = with_manifest_dir(&dir, || {
+            files_except(&["assets/**", "!assets/keep.json"]).unwrap()
+        });
+        files.sort();
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(
+            files,
+            vec![dir.join("assets").join("keep.json"), dir.join("build.rs")]
+        );
+    }
+
+    #[test]
+    fn test_layered_ignore_file_precedence() {
+        let dir = temp_dir("layered");
+        write_fixture(
+            &dir,
+            &[
+                ("build.rs", ""),
+                ("vendor/keep.c", ""),
+                ("vendor/drop.c", ""),
+            ],
+        );
+        let ignore_file = dir.join(".rerunignore");
+        fs::write(&ignore_file, "vendor/*\n").unwrap();
+
+        let mut files = with_manifest_dir(&dir, || {
+            RerunExcept::new()
+                .globs(&["!vendor/keep.c"])
+                .add_ignore_file(&ignore_file)
+                .files()
+                .unwrap()
+        });
+        files.sort();
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(
+            files,
+            vec![dir.join("build.rs"), dir.join("vendor").join("keep.c")]
+        );
     }
 }